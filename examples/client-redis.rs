@@ -1,16 +1,25 @@
-use mini_redis::{client, Result};
+use mini_redis::Result;
+use mini_redis_rust::auth::UserId;
+use mini_redis_rust::reconnect::ReconnectingClient;
 use std::io;
 #[tokio::main]
 async fn main() -> Result<()> {
-    // open a connection to the mini redis server.
-    let mut client = client::connect("127.0.0.1:6969").await?;
-    
+    // open a connection to the mini redis server. unlike a bare `Client`, this one survives a
+    // server restart: it reconnects with backoff and re-authenticates instead of dying on the
+    // first I/O error
+    let mut client = ReconnectingClient::connect("127.0.0.1:6969")
+        .await?
+        // if the server was started with --require-auth, this needs a real user id/secret;
+        // anonymous is accepted by default
+        .with_auth(UserId::new_anonymous(), b"".to_vec())
+        .await?;
+
     loop {
         // set{value:key}
 
         let mut input=String::new();
         io::stdin().read_line(&mut input).expect("error reading the message");
-        let key=input.trim().split(':').nth(0).unwrap().to_string();
+        let key=input.trim().split(':').next().unwrap().to_string();
         let value=input.trim().split(':').nth(1).unwrap().to_string();
         client.set(&key, value.into()).await?;
 
@@ -21,8 +30,6 @@ async fn main() -> Result<()> {
         let string_result = std::str::from_utf8(&bytes).unwrap();
         println!("got value from the server; result={}", string_result);
         }
-    
+
     }
-    //to make sure the return type is satisfied
-    Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,156 @@
+//! A small pluggable authentication layer sitting in front of `Get`/`Set`.
+//!
+//! Identity is a flat 16-byte `UserId`; the all-zero id is the anonymous
+//! user and is allowed by default so existing plaintext, unauthenticated
+//! deployments keep working. `--require-auth` flips that default off.
+
+use std::collections::{HashMap, HashSet};
+
+use subtle::ConstantTimeEq;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct UserId(pub [u8; 16]);
+
+impl UserId {
+    pub const ANONYMOUS: UserId = UserId([0u8; 16]);
+
+    pub fn new_anonymous() -> Self {
+        Self::ANONYMOUS
+    }
+}
+
+impl TryFrom<&[u8]> for UserId {
+    type Error = &'static str;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let arr: [u8; 16] = bytes.try_into().map_err(|_| "user id must be exactly 16 bytes")?;
+        Ok(UserId(arr))
+    }
+}
+
+struct Credential {
+    secret: Vec<u8>,
+    //keys this user may Get/Set; None means unrestricted
+    scope: Option<HashSet<String>>,
+}
+
+//server-side table of known users; cheap to clone behind an Arc since it's only read after setup
+pub struct AuthStore {
+    users: HashMap<UserId, Credential>,
+}
+
+impl AuthStore {
+    //anonymous is permitted with an empty secret unless the caller removes it
+    pub fn new() -> Self {
+        let mut users = HashMap::new();
+        users.insert(UserId::ANONYMOUS, Credential { secret: Vec::new(), scope: None });
+        Self { users }
+    }
+
+    pub fn add_user(&mut self, id: UserId, secret: impl Into<Vec<u8>>, scope: Option<HashSet<String>>) {
+        self.users.insert(id, Credential { secret: secret.into(), scope });
+    }
+
+    pub fn remove_anonymous(&mut self) {
+        self.users.remove(&UserId::ANONYMOUS);
+    }
+
+    pub fn authenticate(&self, id: UserId, secret: &[u8]) -> Option<Session> {
+        let credential = self.users.get(&id)?;
+        // each comparison runs in constant time, but a length mismatch returns before the bytes
+        // are compared, so the timing does reveal whether the lengths match
+        let matches = credential.secret.len().ct_eq(&secret.len()).unwrap_u8() == 1
+            && credential.secret.ct_eq(secret).unwrap_u8() == 1;
+        if !matches {
+            return None;
+        }
+        Some(Session { user: id, scope: credential.scope.clone() })
+    }
+}
+
+impl Default for AuthStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//authenticated state for one connection; cloned out from under a lock by callers that share
+//a session across several concurrent readers (see `mini_redis_rust`'s QUIC server loop)
+#[derive(Clone)]
+pub struct Session {
+    pub user: UserId,
+    scope: Option<HashSet<String>>,
+}
+
+impl Session {
+    pub fn anonymous() -> Self {
+        Session { user: UserId::ANONYMOUS, scope: None }
+    }
+
+    pub fn can_access(&self, key: &str) -> bool {
+        self.scope.as_ref().is_none_or(|scope| scope.contains(key))
+    }
+}
+
+#[cfg(test)]
+mod auth_tests {
+    use super::*;
+
+    #[test]
+    fn test_user_id_from_16_bytes() {
+        let bytes = [7u8; 16];
+        assert_eq!(UserId::try_from(&bytes[..]).unwrap(), UserId(bytes));
+    }
+
+    #[test]
+    fn test_user_id_rejects_wrong_length() {
+        assert!(UserId::try_from(&[1u8, 2, 3][..]).is_err());
+        assert!(UserId::try_from(&[0u8; 17][..]).is_err());
+    }
+
+    #[test]
+    fn test_anonymous_is_authenticated_by_default() {
+        let store = AuthStore::new();
+        let session = store.authenticate(UserId::new_anonymous(), b"").unwrap();
+        assert_eq!(session.user, UserId::ANONYMOUS);
+    }
+
+    #[test]
+    fn test_remove_anonymous_requires_real_credentials() {
+        let mut store = AuthStore::new();
+        store.remove_anonymous();
+        assert!(store.authenticate(UserId::new_anonymous(), b"").is_none());
+    }
+
+    #[test]
+    fn test_authenticate_rejects_wrong_secret() {
+        let mut store = AuthStore::new();
+        let id = UserId([1u8; 16]);
+        store.add_user(id, b"correct".to_vec(), None);
+        assert!(store.authenticate(id, b"wrong").is_none());
+        assert!(store.authenticate(id, b"correct").is_some());
+    }
+
+    #[test]
+    fn test_authenticate_rejects_unknown_user() {
+        let store = AuthStore::new();
+        assert!(store.authenticate(UserId([9u8; 16]), b"").is_none());
+    }
+
+    #[test]
+    fn test_session_with_no_scope_can_access_anything() {
+        let session = Session::anonymous();
+        assert!(session.can_access("any_key"));
+    }
+
+    #[test]
+    fn test_session_scope_restricts_access() {
+        let mut store = AuthStore::new();
+        let id = UserId([2u8; 16]);
+        let scope = HashSet::from(["allowed".to_string()]);
+        store.add_user(id, b"secret".to_vec(), Some(scope));
+        let session = store.authenticate(id, b"secret").unwrap();
+        assert!(session.can_access("allowed"));
+        assert!(!session.can_access("forbidden"));
+    }
+}
@@ -0,0 +1,108 @@
+//! A small `Client` built on our own `FrameTransport`, used instead of
+//! `mini_redis::client` wherever a feature needs to reach past plaintext TCP
+//! (TLS, encryption, reconnect). There is no constructor for a `Client` over
+//! the compressed transport.
+
+use bytes::Bytes;
+use mini_redis::{Frame, Result};
+use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio_rustls::{rustls::ClientConfig, rustls::pki_types::ServerName, TlsConnector};
+use std::sync::Arc;
+
+use crate::connection::{Connection, FrameTransport};
+use crate::crypto::EncryptedConnection;
+
+//generic over the transport rather than the raw stream, so the same client logic works
+//whether frames are carried plain, over TLS, or sealed end-to-end
+pub struct Client<T = Connection<TcpStream>> {
+    connection: T,
+}
+
+impl Client<Connection<TcpStream>> {
+    pub async fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        Ok(Self { connection: Connection::new(stream) })
+    }
+}
+
+impl Client<Connection<tokio_rustls::client::TlsStream<TcpStream>>> {
+    //dials `addr` over plain TCP, then upgrades the connection with a TLS handshake before any framing happens
+    pub async fn connect_tls<A: ToSocketAddrs>(
+        addr: A,
+        config: Arc<ClientConfig>,
+        server_name: &str,
+    ) -> Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        let connector = TlsConnector::from(config);
+        let name = ServerName::try_from(server_name.to_string())
+            .map_err(|_| "invalid server name for TLS handshake")?;
+        let tls_stream = connector.connect(name, stream).await?;
+        Ok(Self { connection: Connection::new_tls(tls_stream) })
+    }
+}
+
+impl Client<EncryptedConnection<TcpStream>> {
+    //dials `addr`, then runs the X25519 handshake before any framing happens -- analogous to
+    //`connect_tls`, but sealing frames ourselves instead of handing that off to rustls
+    pub async fn connect_encrypted<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        let connection = EncryptedConnection::handshake_client(stream).await?;
+        Ok(Self { connection })
+    }
+}
+
+impl<T: FrameTransport> Client<T> {
+    //sends `AUTH <userid> <secret>`; the id is the raw 16 bytes, not a hex/text encoding
+    pub async fn auth(&mut self, id: crate::auth::UserId, secret: &[u8]) -> Result<()> {
+        let frame = Frame::Array(vec![
+            Frame::Bulk(Bytes::from("auth")),
+            Frame::Bulk(Bytes::copy_from_slice(&id.0)),
+            Frame::Bulk(Bytes::copy_from_slice(secret)),
+        ]);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(_) => Ok(()),
+            frame => Err(format!("unexpected response for AUTH: {:?}", frame).into()),
+        }
+    }
+
+    pub async fn get(&mut self, key: &str) -> Result<Option<Bytes>> {
+        let frame = Frame::Array(vec![
+            Frame::Bulk(Bytes::from("get")),
+            Frame::Bulk(Bytes::from(key.to_string())),
+        ]);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Bulk(val) => Ok(Some(val)),
+            Frame::Null => Ok(None),
+            frame => Err(format!("unexpected response for GET: {:?}", frame).into()),
+        }
+    }
+
+    pub async fn set(&mut self, key: &str, value: Bytes) -> Result<()> {
+        let frame = Frame::Array(vec![
+            Frame::Bulk(Bytes::from("set")),
+            Frame::Bulk(Bytes::from(key.to_string())),
+            Frame::Bulk(value),
+        ]);
+        self.connection.write_frame(&frame).await?;
+        match self.read_response().await? {
+            Frame::Simple(_) => Ok(()),
+            frame => Err(format!("unexpected response for SET: {:?}", frame).into()),
+        }
+    }
+
+    async fn read_response(&mut self) -> Result<Frame> {
+        match self.connection.read_frame().await? {
+            Some(Frame::Error(msg)) => Err(msg.into()),
+            Some(frame) => Ok(frame),
+            // a real `io::Error` rather than a bare string, so callers like `ReconnectingClient`
+            // can downcast to tell this apart from an application-level `Frame::Error`
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "connection closed by server",
+            )
+            .into()),
+        }
+    }
+}
@@ -1,20 +1,56 @@
-use bytes::{Bytes, BytesMut,Buf};
+use bytes::{BytesMut,Buf};
 use mini_redis::frame;
-use tokio::net::{TcpListener,TcpStream};
-use tokio::io::{AsyncReadExt, AsyncWriteExt,BufWriter};
+use tokio::net::TcpStream;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt,BufWriter,ReadBuf};
 use mini_redis::{Frame, Result,};
 use std::io::{self, Cursor};
+use std::pin::Pin;
+use std::task::{Context, Poll};
 
+//common surface for anything `process` can read/write frames over -- a plain `Connection`,
+//or a layer that takes its place over the raw stream (e2e encryption, compression)
+pub trait FrameTransport{
+    fn read_frame(&mut self)->impl std::future::Future<Output=Result<Option<Frame>>>+Send;
+    fn write_frame(&mut self,frame:&Frame)->impl std::future::Future<Output=io::Result<()>>+Send;
+}
 
-
-pub struct Connection{
-    stream:BufWriter<TcpStream>,
+// `Connection` is generic over the stream type so it works the same way
+// whether it's wrapping a raw `TcpStream` or a `tokio_rustls` TLS stream --
+// `read_frame`/`parse_frame`/`write_frame` only ever need `AsyncRead`/`AsyncWrite`.
+pub struct Connection<S = TcpStream>{
+    stream:BufWriter<S>,
     buffer:BytesMut,
 }
-impl Connection{
-    pub  fn new(stream:TcpStream)->Self{
+impl<S: AsyncRead + AsyncWrite + Unpin> Connection<S>{
+    pub  fn new(stream:S)->Self{
         let  buffer=BytesMut::with_capacity(4096);
-        Self{stream:BufWriter::new(stream),buffer:buffer}
+        Self{stream:BufWriter::new(stream),buffer}
+    }
+    //alias for `new`, kept around so call sites that wrap a TLS stream read as intentional
+    pub fn new_tls(stream:S)->Self{
+        Self::new(stream)
+    }
+    //unwraps the underlying stream, discarding any buffered-but-unparsed bytes;
+    //used by the e2e encryption layer to encode a frame into an in-memory buffer
+    pub fn into_inner(self)->S{
+        self.stream.into_inner()
+    }
+    //client side of the e2e-encryption handshake, negotiated before any frames are exchanged
+    pub async fn new_encrypted_client(stream:S)->Result<crate::crypto::EncryptedConnection<S>>{
+        crate::crypto::EncryptedConnection::handshake_client(stream).await
+    }
+    //server side of the e2e-encryption handshake
+    pub async fn new_encrypted_server(stream:S)->Result<crate::crypto::EncryptedConnection<S>>{
+        crate::crypto::EncryptedConnection::handshake_server(stream).await
+    }
+    //negotiates a compression codec with the server (client side), then switches the
+    //connection over to the compressed wire format
+    pub async fn with_compression_client(stream:S,supported:&[crate::compression::Codec],threshold:usize)->Result<crate::compression::CompressedConnection<S>>{
+        crate::compression::CompressedConnection::negotiate_client(stream,supported,threshold).await
+    }
+    //server side of the same negotiation
+    pub async fn with_compression_server(stream:S,supported:&[crate::compression::Codec],threshold:usize)->Result<crate::compression::CompressedConnection<S>>{
+        crate::compression::CompressedConnection::negotiate_server(stream,supported,threshold).await
     }
     pub async fn read_frame(&mut self)->Result<Option<Frame>>{
     loop {
@@ -112,6 +148,59 @@ impl Connection{
     }
 }
 
+//upper bound on a single encrypted/compressed frame's on-wire length, shared by `crypto` and
+//`compression` -- both read a `u32` length prefix straight off the socket before allocating a
+//buffer for it, so an unbounded prefix lets a peer force an arbitrarily large allocation before
+//any of the frame's actual contents have been validated
+pub(crate) const MAX_FRAME_LEN: u32 = 16 * 1024 * 1024;
+
+//a sink for `encode_frame` to write into: `Connection::new` needs `AsyncRead + AsyncWrite`, but
+//encoding a frame to memory never reads anything back, so this just reports EOF
+struct WriteOnlyBuf(Vec<u8>);
+
+impl AsyncRead for WriteOnlyBuf{
+    fn poll_read(self: Pin<&mut Self>,_cx:&mut Context<'_>,_buf:&mut ReadBuf<'_>)->Poll<io::Result<()>>{
+        Poll::Ready(Ok(()))
+    }
+}
+impl AsyncWrite for WriteOnlyBuf{
+    fn poll_write(mut self: Pin<&mut Self>,cx:&mut Context<'_>,buf:&[u8])->Poll<io::Result<usize>>{
+        Pin::new(&mut self.0).poll_write(cx,buf)
+    }
+    fn poll_flush(mut self: Pin<&mut Self>,cx:&mut Context<'_>)->Poll<io::Result<()>>{
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+    fn poll_shutdown(mut self: Pin<&mut Self>,cx:&mut Context<'_>)->Poll<io::Result<()>>{
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+//encodes a frame the same way `Connection::write_frame` would, but into memory instead of onto
+//the wire -- shared by the encryption and compression layers, which both need to seal/shrink
+//the encoded bytes before they ever touch the socket
+pub(crate) async fn encode_frame(frame:&Frame)->Result<Vec<u8>>{
+    let mut conn=Connection::new(WriteOnlyBuf(Vec::new()));
+    conn.write_frame(frame).await?;
+    Ok(conn.into_inner().0)
+}
+
+//reverses `encode_frame`: runs the existing `Frame::check`/`Frame::parse` over the plaintext
+pub(crate) fn decode_frame(plaintext:&[u8])->Result<Frame>{
+    let mut buf=Cursor::new(plaintext);
+    Frame::check(&mut buf)?;
+    buf.set_position(0);
+    Frame::parse(&mut buf).map_err(Into::into)
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> FrameTransport for Connection<S>{
+    async fn read_frame(&mut self)->Result<Option<Frame>>{
+        Connection::read_frame(self).await
+    }
+    async fn write_frame(&mut self,frame:&Frame)->io::Result<()>{
+        Connection::write_frame(self,frame).await
+    }
+}
+
 #[cfg(test)]
 mod connection_tests {
     use super::*;
@@ -0,0 +1,226 @@
+//! End-to-end encrypted framing, negotiated with an ephemeral X25519
+//! handshake and sealed with AES-256-GCM. This sits *underneath*
+//! `Connection::read_frame`/`write_frame` rather than replacing them: a
+//! frame is still encoded with the normal RESP wire format, the result is
+//! just sealed before it hits the socket.
+//!
+//! Wire format per message: `[u32 length][12-byte nonce][ciphertext+tag]`.
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use mini_redis::{Frame, Result};
+use rand_core::OsRng;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use x25519_dalek::{EphemeralSecret, PublicKey};
+use zeroize::Zeroize;
+
+use crate::connection::{decode_frame, encode_frame, FrameTransport, MAX_FRAME_LEN};
+
+// The shared secret is hashed with a direction label so the two directions
+// never encrypt under the same key -- otherwise two independent
+// per-direction nonce counters starting at zero could collide under a
+// single shared key and break AES-GCM's one-nonce-per-key-per-message rule.
+fn derive_key(shared_secret: &[u8], label: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(shared_secret);
+    hasher.update(label);
+    hasher.finalize().into()
+}
+
+fn nonce_from_counter(counter: u64) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[4..].copy_from_slice(&counter.to_be_bytes());
+    nonce
+}
+
+pub struct EncryptedConnection<S> {
+    stream: S,
+    send_key: [u8; 32],
+    recv_key: [u8; 32],
+    send_counter: u64,
+    recv_counter: u64,
+}
+
+impl<S> Drop for EncryptedConnection<S> {
+    fn drop(&mut self) {
+        self.send_key.zeroize();
+        self.recv_key.zeroize();
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> EncryptedConnection<S> {
+    const C2S: &'static [u8] = b"mini-redis-rust c2s";
+    const S2C: &'static [u8] = b"mini-redis-rust s2c";
+
+    //client side of the handshake: send our ephemeral public key first, then read the server's
+    pub async fn handshake_client(mut stream: S) -> Result<Self> {
+        let secret = EphemeralSecret::random_from_rng(OsRng);
+        let public = PublicKey::from(&secret);
+        stream.write_all(public.as_bytes()).await?;
+
+        let mut peer_bytes = [0u8; 32];
+        stream.read_exact(&mut peer_bytes).await?;
+        let shared = secret.diffie_hellman(&PublicKey::from(peer_bytes));
+
+        Ok(Self {
+            stream,
+            send_key: derive_key(shared.as_bytes(), Self::C2S),
+            recv_key: derive_key(shared.as_bytes(), Self::S2C),
+            send_counter: 0,
+            recv_counter: 0,
+        })
+    }
+
+    //server side: read the client's public key first, then reply with ours
+    pub async fn handshake_server(mut stream: S) -> Result<Self> {
+        let secret = EphemeralSecret::random_from_rng(OsRng);
+        let public = PublicKey::from(&secret);
+
+        let mut peer_bytes = [0u8; 32];
+        stream.read_exact(&mut peer_bytes).await?;
+        stream.write_all(public.as_bytes()).await?;
+        let shared = secret.diffie_hellman(&PublicKey::from(peer_bytes));
+
+        Ok(Self {
+            stream,
+            send_key: derive_key(shared.as_bytes(), Self::S2C),
+            recv_key: derive_key(shared.as_bytes(), Self::C2S),
+            send_counter: 0,
+            recv_counter: 0,
+        })
+    }
+
+    pub async fn write_frame(&mut self, frame: &Frame) -> Result<()> {
+        let plaintext = encode_frame(frame).await?;
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.send_key));
+        let nonce = nonce_from_counter(self.send_counter);
+        self.send_counter += 1;
+
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+            .map_err(|_| "failed to seal frame")?;
+
+        self.stream.write_u32(12 + ciphertext.len() as u32).await?;
+        self.stream.write_all(&nonce).await?;
+        self.stream.write_all(&ciphertext).await?;
+        Ok(())
+    }
+
+    pub async fn read_frame(&mut self) -> Result<Option<Frame>> {
+        let len = match self.stream.read_u32().await {
+            Ok(len) => len,
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        if len < 12 {
+            return Err("encrypted frame shorter than a nonce".into());
+        }
+        if len > MAX_FRAME_LEN {
+            return Err("encrypted frame exceeds the maximum allowed length".into());
+        }
+
+        let mut nonce = [0u8; 12];
+        self.stream.read_exact(&mut nonce).await?;
+        // the nonce is on the wire rather than implicit, but it still has to match our side's
+        // counter -- otherwise a replayed or reordered message would decrypt and verify just
+        // fine at a point in the stream it was never meant to be read at
+        if nonce != nonce_from_counter(self.recv_counter) {
+            return Err("out-of-order or replayed frame nonce, aborting connection".into());
+        }
+        let mut ciphertext = vec![0u8; len as usize - 12];
+        self.stream.read_exact(&mut ciphertext).await?;
+
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.recv_key));
+        // a failed tag check means either corruption or tampering -- either way the
+        // connection can no longer be trusted, so we bail out instead of trying to resync
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| "AEAD tag verification failed, aborting connection")?;
+        self.recv_counter += 1;
+
+        decode_frame(&plaintext).map(Some)
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> FrameTransport for EncryptedConnection<S>{
+    async fn read_frame(&mut self)->Result<Option<Frame>>{
+        EncryptedConnection::read_frame(self).await
+    }
+    async fn write_frame(&mut self,frame:&Frame)->std::io::Result<()>{
+        EncryptedConnection::write_frame(self,frame).await.map_err(std::io::Error::other)
+    }
+}
+
+#[cfg(test)]
+mod crypto_tests {
+    use super::*;
+    use bytes::Bytes;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    async fn handshaken_pair() -> (EncryptedConnection<TcpStream>, EncryptedConnection<TcpStream>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            EncryptedConnection::handshake_server(socket).await.unwrap()
+        });
+
+        let client_stream = TcpStream::connect(addr).await.unwrap();
+        let client = EncryptedConnection::handshake_client(client_stream).await.unwrap();
+        let server = server.await.unwrap();
+
+        (client, server)
+    }
+
+    #[tokio::test]
+    async fn test_handshake_and_encrypted_roundtrip() {
+        let (mut client, mut server) = handshaken_pair().await;
+
+        let frame = Frame::Array(vec![
+            Frame::Bulk(Bytes::from("set")),
+            Frame::Bulk(Bytes::from("key")),
+            Frame::Bulk(Bytes::from("value")),
+        ]);
+        client.write_frame(&frame).await.unwrap();
+        match server.read_frame().await.unwrap() {
+            Some(Frame::Array(items)) => assert_eq!(items.len(), 3),
+            other => panic!("expected Array frame, got {:?}", other),
+        }
+
+        // and the other direction, under its own key
+        server.write_frame(&Frame::Simple("OK".to_string())).await.unwrap();
+        match client.read_frame().await.unwrap() {
+            Some(Frame::Simple(s)) => assert_eq!(s, "OK"),
+            other => panic!("expected Simple frame, got {:?}", other),
+        }
+    }
+
+    //a single flipped ciphertext byte must fail the AEAD tag check, not silently decrypt to
+    //garbage -- reconstructs the exact `[len][nonce][ciphertext]` wire format `read_frame`
+    //expects, so this is indistinguishable from what a tampering peer would send
+    #[tokio::test]
+    async fn test_tampered_ciphertext_is_rejected() {
+        let (mut client, mut server) = handshaken_pair().await;
+
+        client.write_frame(&Frame::Bulk(Bytes::from("hello"))).await.unwrap();
+
+        let len = server.stream.read_u32().await.unwrap();
+        let mut nonce = [0u8; 12];
+        server.stream.read_exact(&mut nonce).await.unwrap();
+        let mut ciphertext = vec![0u8; len as usize - 12];
+        server.stream.read_exact(&mut ciphertext).await.unwrap();
+        ciphertext[0] ^= 0xff;
+
+        // resend the tampered bytes from the client in exactly the shape the server's `read_frame`
+        // expects to pull them off the wire, then assert it rejects them instead of returning a frame
+        client.stream.write_u32(12 + ciphertext.len() as u32).await.unwrap();
+        client.stream.write_all(&nonce).await.unwrap();
+        client.stream.write_all(&ciphertext).await.unwrap();
+        client.stream.flush().await.unwrap();
+
+        assert!(server.read_frame().await.is_err());
+    }
+}
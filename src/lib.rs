@@ -0,0 +1,18 @@
+//! Shared plumbing for the mini-redis-rust binaries.
+//!
+//! The `mini_redis` crate (from the tokio tutorial) still provides the wire
+//! format (`Frame`, `frame::Error`, `Result`) and the base `Command` parsing.
+//! Everything that needs to be generic over the transport -- TLS, our own
+//! handshakes, compression, auth -- lives here instead, since those can't be
+//! bolted onto the upstream `Connection`/`client` types.
+
+pub mod auth;
+pub mod compression;
+pub mod connection;
+pub mod crypto;
+pub mod quic;
+pub mod tls;
+pub mod client;
+pub mod reconnect;
+
+pub use connection::Connection;
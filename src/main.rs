@@ -1,16 +1,97 @@
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use tokio::net::{TcpListener, TcpStream};
-use mini_redis::{frame, Connection, Frame};
+use tokio::net::TcpListener;
+use mini_redis::Frame;
+use mini_redis_rust::auth::{AuthStore, Session};
+use mini_redis_rust::tls;
+use mini_redis_rust::connection::FrameTransport;
+use mini_redis_rust::Connection;
 type Shardeddb=Arc<Vec<Mutex<HashMap<String,Vec<u8>>>>>;
 use bytes::Bytes;
+
+#[cfg(test)]
+mod tests;
 //number of shards
 const N:u8=32;
 
 //our local host
 const ADDRESS:&str="127.0.0.1:6969";
+
+//CLI options this server understands; everything optional so plaintext, unauthenticated
+//connections keep working with no flags
+struct Args{
+    tls_cert:Option<PathBuf>,
+    tls_key:Option<PathBuf>,
+    require_auth:bool,
+    //not combined with the other transport flags: when set (and --quic is not), it wins over
+    //--encrypt and --tls-cert/--tls-key
+    compression:bool,
+    //QUIC subsumes TLS, so it reuses --tls-cert/--tls-key for its certificate
+    quic:bool,
+    //end-to-end encryption via an X25519 handshake; ignored under --quic or --compression, and
+    //wins over --tls-cert/--tls-key -- it seals frames itself instead of handing that off to TLS/QUIC
+    encrypt:bool,
+}
+//tiny hand-rolled parser, matching the rest of this crate's no-dependencies-for-plumbing style
+fn parse_args()->Args{
+    let mut args=Args{tls_cert:None,tls_key:None,require_auth:false,compression:false,quic:false,encrypt:false};
+    let mut it=std::env::args().skip(1);
+    while let Some(arg)=it.next(){
+        match arg.as_str(){
+            "--tls-cert"=>args.tls_cert=it.next().map(PathBuf::from),
+            "--tls-key"=>args.tls_key=it.next().map(PathBuf::from),
+            "--require-auth"=>args.require_auth=true,
+            "--compression"=>args.compression=true,
+            "--quic"=>args.quic=true,
+            "--encrypt"=>args.encrypt=true,
+            _=>{}
+        }
+    }
+    args
+}
+//codecs this server accepts, in priority order: the first one the client also offers gets picked
+const SUPPORTED_CODECS:&[mini_redis_rust::compression::Codec]=&[
+    mini_redis_rust::compression::Codec::Zstd,
+    mini_redis_rust::compression::Codec::Lz4,
+    mini_redis_rust::compression::Codec::None,
+];
+//payloads below this size skip compression entirely
+const COMPRESSION_THRESHOLD:usize=256;
+//pulls the lowercased command name out of a request frame so we can special-case AUTH
+//before handing everything else to `mini_redis::Command::from_frame`
+fn frame_command_name(frame:&Frame)->Option<String>{
+    if let Frame::Array(parts)=frame{
+        if let Some(Frame::Bulk(name))=parts.first(){
+            return std::str::from_utf8(name).ok().map(|s| s.to_ascii_lowercase());
+        }
+    }
+    None
+}
+//fresh per-connection auth state: anonymous is pre-authenticated unless --require-auth was passed.
+//shared via `Arc<Mutex<_>>` rather than owned by `process` directly so that transports where one
+//connection fans out into several independent `process` calls (QUIC's one-stream-per-request) can
+//still share a single session instead of re-authenticating every stream
+fn initial_session(require_auth:bool)->Arc<Mutex<Option<Session>>>{
+    Arc::new(Mutex::new(if require_auth{None}else{Some(Session::anonymous())}))
+}
+fn handle_auth(frame:&Frame,store:&AuthStore)->(Frame,Option<Session>){
+    let Frame::Array(parts)=frame else{
+        return (Frame::Error("ERR malformed AUTH".to_string()),None);
+    };
+    let (Some(Frame::Bulk(id_bytes)),Some(Frame::Bulk(secret)))=(parts.get(1),parts.get(2)) else{
+        return (Frame::Error("ERR usage: AUTH <userid> <secret>".to_string()),None);
+    };
+    let Ok(id)=mini_redis_rust::auth::UserId::try_from(&id_bytes[..]) else{
+        return (Frame::Error("ERR invalid user id".to_string()),None);
+    };
+    match store.authenticate(id,secret){
+        Some(session)=>(Frame::Simple("OK".to_string()),Some(session)),
+        None=>(Frame::Error("ERR invalid credentials".to_string()),None),
+    }
+}
 //creates a sharded db using arc and mutex
 fn new_sharded_db()->Shardeddb{
     let mut db=Vec::with_capacity(N as usize);
@@ -29,50 +110,160 @@ fn index(key:&str)->usize{
 
 #[tokio::main]
 async fn main(){
-    //binding a tcplistener and create a new db
-    let listener=TcpListener::bind(ADDRESS).await.unwrap();
+    let args=parse_args();
     let db=new_sharded_db();
+    let mut store=AuthStore::new();
+    if args.require_auth{
+        store.remove_anonymous();
+    }
+    let auth=Arc::new(store);
+    let require_auth=args.require_auth;
+    let compression=args.compression;
+    let encrypt=args.encrypt;
+
+    if args.quic{
+        //QUIC bundles its own TLS, and gives each request/response its own stream instead of
+        //sharing one connection's byte pipe, so it gets its own accept loop entirely
+        let (cert,key)=(args.tls_cert.expect("--quic requires --tls-cert"),args.tls_key.expect("--quic requires --tls-key"));
+        let endpoint=mini_redis_rust::quic::server_endpoint(ADDRESS.parse().unwrap(),&cert,&key).unwrap();
+        while let Some(incoming)=endpoint.accept().await{
+            let db=db.clone();
+            let auth=auth.clone();
+            tokio::spawn(async move{
+                let connection=incoming.await.unwrap();
+                //one session per *connection*, shared across every stream opened on it -- a
+                //client authenticates once and every subsequent Get/Set stream rides on that,
+                //instead of each stream starting back at NOAUTH
+                let session=initial_session(require_auth);
+                loop{
+                    let (send,recv)=match connection.accept_bi().await{
+                        Ok(stream)=>stream,
+                        Err(_)=>break,
+                    };
+                    let db=db.clone();
+                    let auth=auth.clone();
+                    let session=session.clone();
+                    //each QUIC stream carries exactly one request/response, unlike a TCP/TLS
+                    //connection's `process` loop -- so this reads one frame, dispatches it, and
+                    //returns instead of looping back into another `read_frame`. the client
+                    //finishes its send half once it's written the request (see `quic::request`),
+                    //so by the time we get here there's nothing left to read after this frame
+                    tokio::spawn(async move{
+                        let quic_stream=mini_redis_rust::quic::QuicStream::from((send,recv));
+                        let mut conn=Connection::new(quic_stream);
+                        if let Some(frame)=conn.read_frame().await.unwrap(){
+                            let response=dispatch(frame,&db,&auth,&session);
+                            conn.write_frame(&response).await.unwrap();
+                        }
+                    });
+                }
+            });
+        }
+        return;
+    }
+
+    //only stand up a TlsAcceptor if both a cert and a key were given; otherwise we stay plaintext
+    let acceptor=match (args.tls_cert,args.tls_key){
+        (Some(cert),Some(key))=>{
+            let config=tls::server_config(&cert,&key).unwrap();
+            Some(tokio_rustls::TlsAcceptor::from(config))
+        }
+        _=>None,
+    };
+    //binding a tcplistener
+    let listener=TcpListener::bind(ADDRESS).await.unwrap();
     loop{
         //average tokio task and socket handling
         let (socket,_)=listener.accept().await.unwrap();
         let db=db.clone();
-        tokio::spawn(async move{
-            process(socket,db).await
-        });
+        let auth=auth.clone();
+        let session=initial_session(require_auth);
+        if compression{
+            tokio::spawn(async move{
+                let conn=Connection::with_compression_server(socket,SUPPORTED_CODECS,COMPRESSION_THRESHOLD).await.unwrap();
+                process(conn,db,auth,session).await
+            });
+            continue;
+        }
+        if encrypt{
+            tokio::spawn(async move{
+                let conn=Connection::new_encrypted_server(socket).await.unwrap();
+                process(conn,db,auth,session).await
+            });
+            continue;
+        }
+        match acceptor.clone(){
+            Some(acceptor)=>{
+                tokio::spawn(async move{
+                    let tls_stream=acceptor.accept(socket).await.unwrap();
+                    process(Connection::new_tls(tls_stream),db,auth,session).await
+                });
+            }
+            None=>{
+                tokio::spawn(async move{
+                    process(Connection::new(socket),db,auth,session).await
+                });
+            }
+        }
     }
 }
-async fn process(socket: TcpStream,db:Shardeddb) {
+//handles exactly one request frame and returns the response to write back: AUTH updates
+//`session` in place, everything else is checked against it and dispatched to the db. split out
+//of `process` so the QUIC path (one frame per stream) and the TCP/TLS/compression/encrypt path
+//(many frames per connection) can share the same command handling without one looping the other
+fn dispatch(frame:Frame,db:&Shardeddb,auth:&AuthStore,session:&Arc<Mutex<Option<Session>>>)->Frame{
     use mini_redis::Command::{self, Get, Set};
-    //create a connection wrapper to the tcp socket
-    let mut connection=Connection::new(socket);
-    //Keep reading frames
-    while let Some(frame)=connection.read_frame().await.unwrap(){
-        //parsing the frame into a redis command
-        let response=match Command::from_frame(frame).unwrap() {
-            Set(cmd)=>{
-                //getting a shard based on the index and adding the key,value
-                let mut shard=db[index(cmd.key())].lock().unwrap();
-                shard.insert(cmd.key().to_string(), cmd.value().clone().to_vec());
-                Frame::Simple("OK".to_string())
+    //AUTH is handled before the frame ever reaches `Command::from_frame`, since the upstream
+    //`Command` enum has no variant for it
+    if frame_command_name(&frame).as_deref()==Some("auth"){
+        let (response,new_session)=handle_auth(&frame,auth);
+        if new_session.is_some(){
+            *session.lock().unwrap()=new_session;
+        }
+        return response;
+    }
+    //cloned out from under the lock so we never hold it across the db lock below
+    let active_session=session.lock().unwrap().clone();
+    let Some(active_session)=&active_session else{
+        return Frame::Error("NOAUTH authentication required".to_string());
+    };
+    //parsing the frame into a redis command
+    match Command::from_frame(frame).unwrap() {
+        Set(cmd)=>{
+            if !active_session.can_access(cmd.key()){
+                Frame::Error("NOPERM no access to this key".to_string())
+            } else {
+            //getting a shard based on the index and adding the key,value
+            let mut shard=db[index(cmd.key())].lock().unwrap();
+            shard.insert(cmd.key().to_string(), cmd.value().clone().to_vec());
+            Frame::Simple("OK".to_string())
             }
-            Get(cmd)=>{
-                //same logic just getting the value from the key 
-                let idx=index(cmd.key());
-                let shard=db[idx].lock().unwrap();
-                //if the key is valid we return a value if it isnt we return a Null frame
-                if let Some(value)=shard.get(cmd.key()){
-                    Frame::Bulk(Bytes::from(value.clone()))
-                }
-                else{
-                    Frame::Null
-                }
+        }
+        Get(cmd)=>{
+            if !active_session.can_access(cmd.key()){
+                Frame::Error("NOPERM no access to this key".to_string())
+            } else {
+            //same logic just getting the value from the key
+            let idx=index(cmd.key());
+            let shard=db[idx].lock().unwrap();
+            //if the key is valid we return a value if it isnt we return a Null frame
+            if let Some(value)=shard.get(cmd.key()){
+                Frame::Bulk(Bytes::from(value.clone()))
             }
-            cmd => panic!("unimplemented {:?}", cmd),
-        };
-        //writing the frame
+            else{
+                Frame::Null
+            }
+            }
+        }
+        cmd => panic!("unimplemented {:?}", cmd),
+    }
+}
+async fn process<T: FrameTransport>(mut connection: T,db:Shardeddb,auth:Arc<AuthStore>,session:Arc<Mutex<Option<Session>>>) {
+    //Keep reading frames
+    while let Some(frame)=connection.read_frame().await.unwrap(){
+        let response=dispatch(frame,&db,&auth,&session);
         connection.write_frame(&response).await.unwrap();
     }
-    
 }
 
 
@@ -0,0 +1,270 @@
+//! QUIC transport: each `Get`/`Set` request/response pair gets its own
+//! bidirectional stream on a single QUIC connection, so one slow request
+//! doesn't head-of-line-block the others the way a single TCP connection
+//! would. TLS is built into QUIC, so this transport doesn't need the
+//! `tls`/`crypto` layers on top.
+
+use bytes::Bytes;
+use mini_redis::{Frame, Result};
+use quinn::{Endpoint, RecvStream, SendStream};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::connection::Connection;
+
+//glues a QUIC bidirectional stream's two halves together into one `AsyncRead + AsyncWrite`
+//type, which is all `Connection<S>` needs
+pub struct QuicStream {
+    send: SendStream,
+    recv: RecvStream,
+}
+
+impl From<(SendStream, RecvStream)> for QuicStream {
+    fn from((send, recv): (SendStream, RecvStream)) -> Self {
+        Self { send, recv }
+    }
+}
+
+impl QuicStream {
+    //tells the peer there's no more data coming on this stream's send half. quinn also finishes
+    //a `SendStream` implicitly when it is dropped; calling this signals EOF earlier, while the
+    //`QuicStream` is still in use
+    fn finish(&mut self) -> Result<()> {
+        self.send.finish().map_err(Into::into)
+    }
+}
+
+//`SendStream`/`RecvStream` both have inherent `poll_*` methods alongside their `AsyncRead`/
+//`AsyncWrite` impls, with different signatures (the inherent ones return quinn's own error
+//types); calling through the trait explicitly picks the `io::Error`-returning impl instead of
+//letting inherent-method resolution shadow it
+impl AsyncRead for QuicStream {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        AsyncRead::poll_read(Pin::new(&mut self.recv), cx, buf)
+    }
+}
+
+impl AsyncWrite for QuicStream {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        AsyncWrite::poll_write(Pin::new(&mut self.send), cx, buf)
+    }
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        AsyncWrite::poll_flush(Pin::new(&mut self.send), cx)
+    }
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        AsyncWrite::poll_shutdown(Pin::new(&mut self.send), cx)
+    }
+}
+
+//binds a QUIC endpoint presenting the given cert/key; `main` uses this instead of a `TcpListener` when `--quic` is passed
+pub fn server_endpoint(bind_addr: SocketAddr, cert_path: &std::path::Path, key_path: &std::path::Path) -> Result<Endpoint> {
+    let rustls_config = crate::tls::server_config(cert_path, key_path)?;
+    let quic_crypto = quinn::crypto::rustls::QuicServerConfig::try_from((*rustls_config).clone())?;
+    let server_config = quinn::ServerConfig::with_crypto(Arc::new(quic_crypto));
+    Ok(Endpoint::server(server_config, bind_addr)?)
+}
+
+//client-side helper that opens one request/response stream per call, mirroring `client::Client`'s `get`/`set`
+pub struct QuicClient {
+    connection: quinn::Connection,
+}
+
+impl QuicClient {
+    pub async fn connect(endpoint: &Endpoint, addr: SocketAddr, server_name: &str) -> Result<Self> {
+        let connection = endpoint.connect(addr, server_name)?.await?;
+        Ok(Self { connection })
+    }
+
+    //authenticates once for the whole `quinn::Connection` -- the server keys its session on the
+    //connection, not the stream, so every `get`/`set` afterwards rides on this one AUTH
+    pub async fn auth(&self, id: crate::auth::UserId, secret: &[u8]) -> Result<()> {
+        let frame = Frame::Array(vec![
+            Frame::Bulk(Bytes::from("auth")),
+            Frame::Bulk(Bytes::copy_from_slice(&id.0)),
+            Frame::Bulk(Bytes::copy_from_slice(secret)),
+        ]);
+        match self.request(&frame).await? {
+            Frame::Simple(_) => Ok(()),
+            frame => Err(format!("unexpected response for AUTH: {:?}", frame).into()),
+        }
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Option<Bytes>> {
+        let frame = Frame::Array(vec![
+            Frame::Bulk(Bytes::from("get")),
+            Frame::Bulk(Bytes::from(key.to_string())),
+        ]);
+        match self.request(&frame).await? {
+            Frame::Bulk(val) => Ok(Some(val)),
+            Frame::Null => Ok(None),
+            frame => Err(format!("unexpected response for GET: {:?}", frame).into()),
+        }
+    }
+
+    pub async fn set(&self, key: &str, value: Bytes) -> Result<()> {
+        let frame = Frame::Array(vec![
+            Frame::Bulk(Bytes::from("set")),
+            Frame::Bulk(Bytes::from(key.to_string())),
+            Frame::Bulk(value),
+        ]);
+        match self.request(&frame).await? {
+            Frame::Simple(_) => Ok(()),
+            frame => Err(format!("unexpected response for SET: {:?}", frame).into()),
+        }
+    }
+
+    async fn request(&self, frame: &Frame) -> Result<Frame> {
+        let (send, recv) = self.connection.open_bi().await?;
+        let mut conn = Connection::new(QuicStream { send, recv });
+        conn.write_frame(frame).await?;
+        // tell the server there's nothing more coming on our send half *before* waiting on the
+        // response, rather than only when this stream is dropped at the end of the function
+        let mut quic_stream = conn.into_inner();
+        quic_stream.finish()?;
+        let mut conn = Connection::new(quic_stream);
+        match conn.read_frame().await? {
+            Some(Frame::Error(msg)) => Err(msg.into()),
+            Some(frame) => Ok(frame),
+            None => Err("stream closed before a response was received".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod quic_tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    type Db = Arc<Mutex<HashMap<String, Bytes>>>;
+
+    fn handle(frame: Frame, db: &Db) -> Frame {
+        let Frame::Array(parts) = frame else { return Frame::Error("ERR expected array".to_string()) };
+        match parts.first() {
+            Some(Frame::Bulk(cmd)) if cmd.as_ref() == b"set" => {
+                let (Some(Frame::Bulk(key)), Some(Frame::Bulk(value))) = (parts.get(1), parts.get(2)) else {
+                    return Frame::Error("ERR usage: set <key> <value>".to_string());
+                };
+                db.lock().unwrap().insert(String::from_utf8_lossy(key).to_string(), value.clone());
+                Frame::Simple("OK".to_string())
+            }
+            Some(Frame::Bulk(cmd)) if cmd.as_ref() == b"get" => {
+                let Some(Frame::Bulk(key)) = parts.get(1) else { return Frame::Error("ERR usage: get <key>".to_string()) };
+                match db.lock().unwrap().get(&String::from_utf8_lossy(key).to_string()) {
+                    Some(value) => Frame::Bulk(value.clone()),
+                    None => Frame::Null,
+                }
+            }
+            _ => Frame::Error("ERR unknown command".to_string()),
+        }
+    }
+
+    //a self-signed cert is enough to exercise the transport; nothing here trusts the identity
+    fn self_signed() -> (rustls::pki_types::CertificateDer<'static>, rustls::pki_types::PrivateKeyDer<'static>) {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        let key = rustls::pki_types::PrivateKeyDer::Pkcs8(cert.key_pair.serialize_der().into());
+        (cert.cert.der().clone(), key)
+    }
+
+    #[derive(Debug)]
+    struct AcceptAnyCert;
+    impl rustls::client::danger::ServerCertVerifier for AcceptAnyCert {
+        fn verify_server_cert(
+            &self,
+            _end_entity: &rustls::pki_types::CertificateDer<'_>,
+            _intermediates: &[rustls::pki_types::CertificateDer<'_>],
+            _server_name: &rustls::pki_types::ServerName<'_>,
+            _ocsp_response: &[u8],
+            _now: rustls::pki_types::UnixTime,
+        ) -> std::result::Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+            Ok(rustls::client::danger::ServerCertVerified::assertion())
+        }
+        fn verify_tls12_signature(&self, _: &[u8], _: &rustls::pki_types::CertificateDer<'_>, _: &rustls::DigitallySignedStruct) -> std::result::Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+            Ok(rustls::client::danger::HandshakeSignatureValid::assertion())
+        }
+        fn verify_tls13_signature(&self, _: &[u8], _: &rustls::pki_types::CertificateDer<'_>, _: &rustls::DigitallySignedStruct) -> std::result::Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+            Ok(rustls::client::danger::HandshakeSignatureValid::assertion())
+        }
+        fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+            rustls::crypto::ring::default_provider().signature_verification_algorithms.supported_schemes()
+        }
+    }
+
+    async fn start_test_endpoint() -> (Endpoint, SocketAddr, Db) {
+        let (cert, key) = self_signed();
+        let server_crypto = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert], key)
+            .unwrap();
+        let quic_crypto = quinn::crypto::rustls::QuicServerConfig::try_from(server_crypto).unwrap();
+        let server_config = quinn::ServerConfig::with_crypto(Arc::new(quic_crypto));
+        let endpoint = Endpoint::server(server_config, "127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = endpoint.local_addr().unwrap();
+
+        let db: Db = Arc::new(Mutex::new(HashMap::new()));
+        let accept_db = db.clone();
+        let accept_endpoint = endpoint.clone();
+        tokio::spawn(async move {
+            while let Some(incoming) = accept_endpoint.accept().await {
+                let db = accept_db.clone();
+                tokio::spawn(async move {
+                    let connection = incoming.await.unwrap();
+                    loop {
+                        let Ok((send, recv)) = connection.accept_bi().await else { break };
+                        let db = db.clone();
+                        tokio::spawn(async move {
+                            let mut conn = Connection::new(QuicStream { send, recv });
+                            if let Ok(Some(frame)) = conn.read_frame().await {
+                                let response = handle(frame, &db);
+                                let _ = conn.write_frame(&response).await;
+                            }
+                        });
+                    }
+                });
+            }
+        });
+
+        (endpoint, addr, db)
+    }
+
+    fn insecure_client_endpoint() -> Endpoint {
+        let mut endpoint = Endpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+        let crypto = rustls::ClientConfig::builder()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyCert))
+            .with_no_client_auth();
+        let quic_crypto = quinn::crypto::rustls::QuicClientConfig::try_from(crypto).unwrap();
+        endpoint.set_default_client_config(quinn::ClientConfig::new(Arc::new(quic_crypto)));
+        endpoint
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_streams_over_one_connection() {
+        let (server_endpoint, addr, _db) = start_test_endpoint().await;
+        let client_endpoint = insecure_client_endpoint();
+        let client = Arc::new(QuicClient::connect(&client_endpoint, addr, "localhost").await.unwrap());
+
+        // many concurrent Get/Set pairs, each on its own stream, sharing the one QUIC connection
+        let handles: Vec<_> = (0..10)
+            .map(|i| {
+                let client = client.clone();
+                tokio::spawn(async move {
+                    let key = format!("quic_key_{}", i);
+                    let value = format!("quic_value_{}", i);
+                    client.set(&key, Bytes::from(value.clone())).await.unwrap();
+                    let result = client.get(&key).await.unwrap();
+                    assert_eq!(result, Some(Bytes::from(value)));
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        server_endpoint.close(0u32.into(), b"test done");
+    }
+}
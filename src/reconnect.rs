@@ -0,0 +1,341 @@
+//! A resilient client wrapper: on an I/O error from the underlying
+//! `Client`, transparently re-dials with exponential backoff (and re-runs
+//! whatever handshake -- AUTH, and TLS/encryption if the transport needs one
+//! -- was used to get there) before retrying the failed command.
+
+use bytes::Bytes;
+use mini_redis::{Error, Result};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::time::sleep;
+
+use crate::auth::UserId;
+use crate::client::Client;
+use crate::connection::{Connection, FrameTransport};
+use crate::crypto::EncryptedConnection;
+
+//re-establishes a `Client<T>` from scratch: a fresh TCP dial plus whatever TLS/encryption
+//handshake `T` needs. Called once up front and again on every reconnect, since those are the
+//same steps -- this is the one place that has to know how to build `T`
+type Dial<T> = Box<dyn Fn() -> Pin<Box<dyn Future<Output = Result<Client<T>>> + Send>> + Send>;
+
+#[derive(Clone, Copy, Debug)]
+pub struct BackoffConfig {
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub max_attempts: u32,
+}
+
+impl Default for BackoffConfig {
+    fn default() -> Self {
+        Self { base_delay: Duration::from_millis(100), max_delay: Duration::from_secs(10), max_attempts: 8 }
+    }
+}
+
+impl BackoffConfig {
+    fn delay_for(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1).min(20));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+//`Client::get`/`set` return this same error type for both a dropped socket and an
+//application-level response (NOAUTH, NOPERM, a malformed frame); only the former should
+//drive a reconnect, so we tell them apart by whether an `io::Error` is underneath
+fn is_transport_error(err: &Error) -> bool {
+    err.downcast_ref::<std::io::Error>().is_some()
+}
+
+//observability hook so callers can log/metric reconnect attempts
+#[derive(Clone, Debug)]
+pub enum ReconnectEvent {
+    Attempting { attempt: u32, delay: Duration },
+    Reconnected,
+    Failed { attempt: u32 },
+}
+
+pub struct ReconnectingClient<T: FrameTransport> {
+    dial: Dial<T>,
+    client: Client<T>,
+    backoff: BackoffConfig,
+    reauth: Option<(UserId, Vec<u8>)>,
+    on_event: Option<Box<dyn FnMut(ReconnectEvent) + Send>>,
+}
+
+impl ReconnectingClient<Connection<TcpStream>> {
+    pub async fn connect(addr: impl Into<String>) -> Result<Self> {
+        let addr = addr.into();
+        Self::connect_with(move || {
+            let addr = addr.clone();
+            Box::pin(async move { Client::connect(addr).await })
+        })
+        .await
+    }
+}
+
+impl ReconnectingClient<EncryptedConnection<TcpStream>> {
+    //same idea as `Client::connect_encrypted`, but the X25519 handshake is re-run on every
+    //reconnect too, since a fresh TCP socket means a fresh (and differently keyed) connection
+    pub async fn connect_encrypted(addr: impl Into<String>) -> Result<Self> {
+        let addr = addr.into();
+        Self::connect_with(move || {
+            let addr = addr.clone();
+            Box::pin(async move { Client::connect_encrypted(addr).await })
+        })
+        .await
+    }
+}
+
+impl<T: FrameTransport> ReconnectingClient<T> {
+    //generic escape hatch for any transport: `dial` is called once up front and again on every
+    //reconnect, so it must fully re-establish the connection itself -- TCP dial plus whatever
+    //TLS/encryption handshake `T` needs (see `connect`/`connect_encrypted` above for the two
+    //transports this crate already builds `Client`s for)
+    pub async fn connect_with<F>(dial: F) -> Result<Self>
+    where
+        F: Fn() -> Pin<Box<dyn Future<Output = Result<Client<T>>> + Send>> + Send + 'static,
+    {
+        let client = dial().await?;
+        Ok(Self { dial: Box::new(dial), client, backoff: BackoffConfig::default(), reauth: None, on_event: None })
+    }
+
+    pub fn with_backoff(mut self, backoff: BackoffConfig) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    //authenticates the current connection, and re-authenticates with these credentials after
+    //every reconnect from here on
+    pub async fn with_auth(mut self, id: UserId, secret: Vec<u8>) -> Result<Self> {
+        self.client.auth(id, &secret).await?;
+        self.reauth = Some((id, secret));
+        Ok(self)
+    }
+
+    pub fn on_event(mut self, callback: impl FnMut(ReconnectEvent) + Send + 'static) -> Self {
+        self.on_event = Some(Box::new(callback));
+        self
+    }
+
+    pub async fn get(&mut self, key: &str) -> Result<Option<Bytes>> {
+        let mut attempt = 0;
+        loop {
+            match self.client.get(key).await {
+                Ok(value) => return Ok(value),
+                Err(e) if is_transport_error(&e) => self.reconnect(&mut attempt).await?,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    pub async fn set(&mut self, key: &str, value: Bytes) -> Result<()> {
+        let mut attempt = 0;
+        loop {
+            match self.client.set(key, value.clone()).await {
+                Ok(()) => return Ok(()),
+                Err(e) if is_transport_error(&e) => self.reconnect(&mut attempt).await?,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    //re-dials with exponential backoff until a connection (and, if configured, AUTH) succeeds
+    async fn reconnect(&mut self, attempt: &mut u32) -> Result<()> {
+        loop {
+            *attempt += 1;
+            if *attempt > self.backoff.max_attempts {
+                return Err("exceeded max reconnect attempts".into());
+            }
+
+            let delay = self.backoff.delay_for(*attempt);
+            self.emit(ReconnectEvent::Attempting { attempt: *attempt, delay });
+            sleep(delay).await;
+
+            match self.try_reconnect_once().await {
+                Ok(client) => {
+                    self.client = client;
+                    self.emit(ReconnectEvent::Reconnected);
+                    return Ok(());
+                }
+                Err(_) => self.emit(ReconnectEvent::Failed { attempt: *attempt }),
+            }
+        }
+    }
+
+    async fn try_reconnect_once(&self) -> Result<Client<T>> {
+        let mut client = (self.dial)().await?;
+        if let Some((id, secret)) = &self.reauth {
+            client.auth(*id, secret).await?;
+        }
+        Ok(client)
+    }
+
+    fn emit(&mut self, event: ReconnectEvent) {
+        if let Some(callback) = &mut self.on_event {
+            callback(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod reconnect_tests {
+    use super::*;
+    use mini_redis::Frame;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tokio::net::TcpListener;
+
+    type Db = Arc<Mutex<HashMap<String, Bytes>>>;
+
+    fn handle(frame: Frame, db: &Db) -> Frame {
+        let Frame::Array(parts) = frame else { return Frame::Error("ERR expected array".to_string()) };
+        let Some(Frame::Bulk(cmd)) = parts.first() else { return Frame::Error("ERR missing command".to_string()) };
+        match std::str::from_utf8(cmd).unwrap_or("").to_ascii_lowercase().as_str() {
+            "get" => {
+                let Some(Frame::Bulk(key)) = parts.get(1) else { return Frame::Error("ERR usage: get <key>".to_string()) };
+                let key = String::from_utf8_lossy(key).to_string();
+                // a stand-in for a NOPERM/NOAUTH response: a protocol-level error on an
+                // otherwise healthy connection, used to test that it doesn't trigger a reconnect
+                if key == "forbidden" {
+                    return Frame::Error("NOPERM no access to this key".to_string());
+                }
+                match db.lock().unwrap().get(&key) {
+                    Some(value) => Frame::Bulk(value.clone()),
+                    None => Frame::Null,
+                }
+            }
+            "set" => {
+                let (Some(Frame::Bulk(key)), Some(Frame::Bulk(value))) = (parts.get(1), parts.get(2)) else {
+                    return Frame::Error("ERR usage: set <key> <value>".to_string());
+                };
+                db.lock().unwrap().insert(String::from_utf8_lossy(key).to_string(), value.clone());
+                Frame::Simple("OK".to_string())
+            }
+            other => Frame::Error(format!("ERR unknown command '{other}'")),
+        }
+    }
+
+    //a minimal single-loop server, just enough to exercise reconnection -- not the sharded-db server in main.rs
+    //connection tasks live in a `JoinSet` owned by this future, so aborting `serve` also drops
+    //every open connection and the client really has to reconnect
+    async fn serve(listener: TcpListener, db: Db) {
+        let mut connections = tokio::task::JoinSet::new();
+        while let Ok((socket, _)) = listener.accept().await {
+            let db = db.clone();
+            connections.spawn(async move {
+                let mut conn = Connection::new(socket);
+                while let Ok(Some(frame)) = conn.read_frame().await {
+                    let response = handle(frame, &db);
+                    if conn.write_frame(&response).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    }
+
+    //same idea as `serve`, but running the X25519 handshake per connection -- used to prove
+    //reconnection also works (and re-runs the handshake) for transports other than plain TCP
+    async fn serve_encrypted(listener: TcpListener, db: Db) {
+        let mut connections = tokio::task::JoinSet::new();
+        while let Ok((socket, _)) = listener.accept().await {
+            let db = db.clone();
+            connections.spawn(async move {
+                let Ok(mut conn) = EncryptedConnection::handshake_server(socket).await else { return };
+                while let Ok(Some(frame)) = conn.read_frame().await {
+                    let response = handle(frame, &db);
+                    if conn.write_frame(&response).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_over_encrypted_transport_after_listener_restart() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let db: Db = Arc::new(Mutex::new(HashMap::new()));
+
+        let server = tokio::spawn(serve_encrypted(listener, db.clone()));
+
+        let mut client = ReconnectingClient::connect_encrypted(addr.to_string())
+            .await
+            .unwrap()
+            .with_backoff(BackoffConfig {
+                base_delay: Duration::from_millis(10),
+                max_delay: Duration::from_millis(50),
+                max_attempts: 50,
+            });
+
+        client.set("key", Bytes::from("value")).await.unwrap();
+
+        // kill the listener, then rebind on the exact same port -- the reconnect has to run a
+        // brand new X25519 handshake against the new listener, not just redial the old socket
+        server.abort();
+        sleep(Duration::from_millis(20)).await;
+        let listener = TcpListener::bind(addr).await.unwrap();
+        tokio::spawn(serve_encrypted(listener, db.clone()));
+
+        let result = client.get("key").await.unwrap();
+        assert_eq!(result, Some(Bytes::from("value")));
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_after_listener_restart() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let db: Db = Arc::new(Mutex::new(HashMap::new()));
+
+        let server = tokio::spawn(serve(listener, db.clone()));
+
+        let mut client = ReconnectingClient::connect(addr.to_string())
+            .await
+            .unwrap()
+            .with_backoff(BackoffConfig {
+                base_delay: Duration::from_millis(10),
+                max_delay: Duration::from_millis(50),
+                max_attempts: 50,
+            });
+
+        client.set("key", Bytes::from("value")).await.unwrap();
+
+        // kill the listener, then rebind on the exact same port
+        server.abort();
+        sleep(Duration::from_millis(20)).await;
+        let listener = TcpListener::bind(addr).await.unwrap();
+        tokio::spawn(serve(listener, db.clone()));
+
+        let result = client.get("key").await.unwrap();
+        assert_eq!(result, Some(Bytes::from("value")));
+    }
+
+    //a protocol-level `Frame::Error` (server is alive, the command itself is bad) must surface
+    //immediately instead of retrying the full reconnect-with-backoff loop
+    #[tokio::test]
+    async fn test_protocol_error_does_not_trigger_reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let db: Db = Arc::new(Mutex::new(HashMap::new()));
+        tokio::spawn(serve(listener, db));
+
+        let mut client = ReconnectingClient::connect(addr.to_string())
+            .await
+            .unwrap()
+            .with_backoff(BackoffConfig {
+                base_delay: Duration::from_millis(10),
+                max_delay: Duration::from_millis(50),
+                max_attempts: 1,
+            });
+
+        // the server is perfectly healthy here -- it just refuses this particular key. With
+        // `max_attempts: 1` a reconnect loop would immediately exhaust itself and report
+        // "exceeded max reconnect attempts" instead of surfacing the real NOPERM error.
+        let err = client.get("forbidden").await.unwrap_err();
+        assert!(!err.to_string().contains("exceeded max reconnect attempts"));
+        assert!(err.to_string().contains("NOPERM"));
+    }
+}
@@ -1,29 +1,45 @@
 #[cfg(test)]
-mod tests {
-    use crate::{new_sharded_db, process, index, N};
+mod tests_mod {
+    use crate::{new_sharded_db, process, index, initial_session, N};
     use tokio::net::TcpListener;
     use tokio::time::{sleep, Duration};
     use mini_redis::client;
+    use mini_redis_rust::auth::{AuthStore, UserId};
+    use mini_redis_rust::Connection;
     use bytes::Bytes;
     use std::net::SocketAddr;
+    use std::sync::Arc;
 
     // Helper function to start a test server
     async fn start_test_server() -> SocketAddr {
+        start_test_server_with(false, |_| {}).await
+    }
+
+    //same as `start_test_server`, but lets a test require auth and/or register scoped users
+    async fn start_test_server_with(require_auth: bool, configure: impl FnOnce(&mut AuthStore)) -> SocketAddr {
         let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
         let addr = listener.local_addr().unwrap();
-        
+
+        let mut store = AuthStore::new();
+        if require_auth {
+            store.remove_anonymous();
+        }
+        configure(&mut store);
+        let auth = Arc::new(store);
+
         // Start server in background
         tokio::spawn(async move {
             let db = new_sharded_db();
             loop {
                 let (socket, _) = listener.accept().await.unwrap();
                 let db = db.clone();
+                let auth = auth.clone();
                 tokio::spawn(async move {
-                    process(socket, db).await
+                    process(Connection::new(socket), db, auth, initial_session(require_auth)).await
                 });
             }
         });
-        
+
         // Give server time to start
         sleep(Duration::from_millis(10)).await;
         addr
@@ -91,12 +107,12 @@ mod tests {
 
         // Set all keys
         for (key, value) in &test_keys {
-            client.set(*key, (*value).into()).await.unwrap();
+            client.set(key, (*value).into()).await.unwrap();
         }
 
         // Get all keys and verify
         for (key, expected_value) in &test_keys {
-            let result = client.get(*key).await.unwrap();
+            let result = client.get(key).await.unwrap();
             assert_eq!(result, Some(Bytes::from(*expected_value)));
         }
     }
@@ -123,7 +139,6 @@ mod tests {
         
         // Spawn multiple concurrent tasks
         let handles: Vec<_> = (0..10).map(|i| {
-            let addr = addr.clone();
             tokio::spawn(async move {
                 let mut client = client::connect(addr).await.unwrap();
                 let key = format!("concurrent_key_{}", i);
@@ -193,8 +208,48 @@ mod tests {
         // Test with special characters
         let special_value = "Hello, 世界! 🦀 ñáéíóú";
         client.set("special_key", special_value.into()).await.unwrap();
-        
+
         let result = client.get("special_key").await.unwrap();
         assert_eq!(result, Some(Bytes::from(special_value)));
     }
+
+    // `mini_redis::client::Client` has no way to send a raw AUTH frame, so the NOAUTH/NOPERM
+    // tests below go through `mini_redis_rust::client::Client` instead, which speaks the same
+    // wire protocol but exposes `.auth()`.
+    #[tokio::test]
+    async fn test_noauth_rejected_before_authenticating() {
+        let addr = start_test_server_with(true, |_| {}).await;
+        let mut client = mini_redis_rust::client::Client::connect(addr).await.unwrap();
+
+        let err = client.get("hello").await.unwrap_err();
+        assert!(err.to_string().contains("NOAUTH"));
+    }
+
+    #[tokio::test]
+    async fn test_authenticated_request_succeeds_once_auth_required_is_on() {
+        let id = UserId([4u8; 16]);
+        let addr = start_test_server_with(true, |store| {
+            store.add_user(id, b"secret", None);
+        }).await;
+        let mut client = mini_redis_rust::client::Client::connect(addr).await.unwrap();
+
+        client.auth(id, b"secret").await.unwrap();
+        client.set("hello", "world".into()).await.unwrap();
+        let result = client.get("hello").await.unwrap();
+        assert_eq!(result, Some(Bytes::from("world")));
+    }
+
+    #[tokio::test]
+    async fn test_noperm_rejected_for_key_outside_session_scope() {
+        let id = UserId([3u8; 16]);
+        let addr = start_test_server_with(true, |store| {
+            store.add_user(id, b"secret", Some(std::collections::HashSet::from(["allowed".to_string()])));
+        }).await;
+        let mut client = mini_redis_rust::client::Client::connect(addr).await.unwrap();
+
+        client.auth(id, b"secret").await.unwrap();
+        client.set("allowed", "ok".into()).await.unwrap();
+        let err = client.get("forbidden").await.unwrap_err();
+        assert!(err.to_string().contains("NOPERM"));
+    }
 }